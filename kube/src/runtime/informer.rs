@@ -1,12 +1,32 @@
 use crate::{
-    api::{ListParams, Meta, Resource, WatchEvent},
-    Client, Result,
+    api::{ListParams, Meta, ObjectList, Resource, WatchEvent},
+    runtime::VersionStore,
+    Client, Error, Result,
 };
 
-use futures::{lock::Mutex, Stream, StreamExt};
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use futures::{lock::Mutex, stream, Stream, StreamExt};
 use serde::de::DeserializeOwned;
-use std::{sync::Arc, time::Duration};
-use tokio::time::delay_for;
+use std::{
+    sync::{Arc, Mutex as SyncMutex},
+    time::{Duration, Instant},
+};
+use tokio::{sync::watch, time::delay_for};
+use tracing::Instrument;
+
+/// The minimum amount of time a watch connection must stay healthy before
+/// we consider it safe to reset the backoff back to its initial interval.
+///
+/// Without this, a watch that connects successfully but errors out again
+/// immediately (e.g. a misbehaving proxy) would reset the backoff on every
+/// single attempt and effectively defeat it.
+const MIN_HEALTHY_DURATION: Duration = Duration::from_secs(60);
+
+/// The minimum amount of time between two persisted-version writes
+///
+/// We only need the last-saved version to be roughly up to date, so we
+/// debounce writes rather than hitting the `VersionStore` on every event.
+const VERSION_SAVE_DEBOUNCE: Duration = Duration::from_secs(2);
 
 /// An event informer for a `Resource`
 ///
@@ -14,17 +34,31 @@ use tokio::time::delay_for;
 /// - seeding the intial resourceVersion with a list call (optional)
 /// - keeping track of resourceVersions during every poll
 /// - recovering when resourceVersions get desynced
+/// - optionally persisting the resourceVersion via a `VersionStore` so a
+///   restart can resume the watch instead of relisting everything
 #[derive(Clone)]
 pub struct Informer<K>
 where
     K: Clone + DeserializeOwned + Meta,
 {
-    version: Arc<Mutex<String>>,
+    version_tx: Arc<watch::Sender<String>>,
+    version_rx: watch::Receiver<String>,
     client: Client,
     resource: Resource,
     params: ListParams,
     needs_resync: Arc<Mutex<bool>>,
     needs_retry: Arc<Mutex<bool>>,
+    // Plain sync mutex: every critical section is a short, non-blocking
+    // struct read/write, never held across an `.await`, so there's no need
+    // to pull in `futures::lock::Mutex` (and `with_backoff` can stay a
+    // regular synchronous setter instead of reaching for `block_on`).
+    backoff: Arc<SyncMutex<ExponentialBackoff>>,
+    connected_since: Arc<Mutex<Option<Instant>>>,
+    bookmarks: bool,
+    list_limit: Option<u32>,
+    max_objects: Option<u32>,
+    store: Option<Arc<dyn VersionStore + Send + Sync>>,
+    last_saved: Arc<Mutex<Option<Instant>>>,
     phantom: std::marker::PhantomData<K>,
 }
 
@@ -34,28 +68,207 @@ where
 {
     /// Create a reflector with a kube client on a kube resource
     pub fn new(client: Client, lp: ListParams, r: Resource) -> Self {
+        let (version_tx, version_rx) = watch::channel(0.to_string());
         Informer {
             client,
             resource: r,
             params: lp,
-            version: Arc::new(Mutex::new(0.to_string())),
+            version_tx: Arc::new(version_tx),
+            version_rx,
             needs_resync: Arc::new(Mutex::new(false)),
             needs_retry: Arc::new(Mutex::new(false)),
+            backoff: Arc::new(SyncMutex::new(Self::default_backoff())),
+            connected_since: Arc::new(Mutex::new(None)),
+            bookmarks: false,
+            list_limit: None,
+            max_objects: None,
+            store: None,
+            last_saved: Arc::new(Mutex::new(None)),
             phantom: std::marker::PhantomData,
         }
     }
 
+    /// Update the stored resourceVersion and notify any `version_rx` watchers
+    fn set_version(&self, v: String) {
+        // Only fails if every receiver (including our own `version_rx`) was
+        // dropped, which can't happen while `self` is alive
+        let _ = self.version_tx.broadcast(v);
+    }
+
+    fn default_backoff() -> ExponentialBackoff {
+        ExponentialBackoff {
+            initial_interval: Duration::from_secs(1),
+            current_interval: Duration::from_secs(1),
+            multiplier: 2.0,
+            max_interval: Duration::from_secs(60),
+            max_elapsed_time: None,
+            ..ExponentialBackoff::default()
+        }
+    }
+
+    /// Override the default backoff used between failed polls
+    ///
+    /// By default we start at 1s, double on every failure, and cap at 60s.
+    /// This lets callers tune that to their own apiserver's tolerance.
+    pub fn with_backoff(self, initial_interval: Duration, multiplier: f64, max_interval: Duration) -> Self {
+        *self.backoff.lock().unwrap() = ExponentialBackoff {
+            initial_interval,
+            current_interval: initial_interval,
+            multiplier,
+            max_interval,
+            max_elapsed_time: None,
+            ..ExponentialBackoff::default()
+        };
+        self
+    }
+
+    /// Enable `allowWatchBookmarks` on the watch request
+    ///
+    /// Bookmarks let the apiserver periodically advance our stored
+    /// resourceVersion even when nothing has changed, which keeps our
+    /// cursor from expiring out of the server's history and avoids a
+    /// spurious 410 Gone / full resync on otherwise quiet resources.
+    ///
+    /// This assumes `ListParams::bookmarks: bool` is sent as the
+    /// `allowWatchBookmarks` query param (set in `poll_inner` below), and
+    /// that the apiserver's bookmark events come back as a
+    /// `WatchEvent::Bookmark(Bookmark)` variant whose `Bookmark` exposes the
+    /// advanced `resource_version: String` (read in `poll_inner`'s event
+    /// interceptor, and ignored as a no-op event in `watcher`'s state machine).
+    pub fn with_bookmarks(mut self, enable: bool) -> Self {
+        self.bookmarks = enable;
+        self
+    }
+
+    /// Page the initial list used to seed the resourceVersion
+    ///
+    /// Without this, `seed` issues a single unpaged `list`, which can spike
+    /// memory for collections with thousands of objects. Setting a limit
+    /// makes `seed` follow the `metadata.continue` token across successive
+    /// requests instead.
+    pub fn with_list_limit(mut self, limit: u32) -> Self {
+        self.list_limit = Some(limit);
+        self
+    }
+
+    /// Cap the number of objects `seed` is willing to materialize
+    ///
+    /// If the accumulated object count would exceed this, `seed` bails out
+    /// with `Error::TooManyObjects` rather than continuing to page through
+    /// a collection that's larger than the caller expected.
+    pub fn with_max_objects(mut self, max: u32) -> Self {
+        self.max_objects = Some(max);
+        self
+    }
+
+    /// Persist the watch cursor to `store`, resuming from it if present
+    ///
+    /// On attach, the last saved resourceVersion (if any) is loaded and
+    /// adopted immediately, so a redeployed controller resumes its watch
+    /// instead of relisting everything. If that version has since fallen
+    /// out of the apiserver's history it will come back as a 410, which
+    /// `poll` already handles by resetting to latest and relisting - so a
+    /// stale or missing cursor always degrades gracefully.
+    pub async fn with_version_store<S>(mut self, store: S) -> Self
+    where
+        S: VersionStore + Send + Sync + 'static,
+    {
+        let store = Arc::new(store);
+        if let Some(v) = store.load().await {
+            info!("Resuming Informer for {} from persisted version {}", self.resource.kind, v);
+            self.set_version(v);
+        }
+        self.store = Some(store);
+        self
+    }
+
+    /// Seed the resourceVersion (and return the listed objects) via `list`
+    ///
+    /// When `with_list_limit` is set this pages through the collection using
+    /// `metadata.continue` rather than issuing a single unbounded `list`.
+    /// The resourceVersion recorded at the end of pagination is the list's
+    /// resourceVersion, which a subsequent `poll` then continues from.
+    ///
+    /// This assumes `ListParams::continue_token: Option<String>` serializes
+    /// to the `continue` query param (set per-page below), that the response
+    /// metadata's `continue_: Option<String>` comes back empty once the last
+    /// page has been reached and `resource_version: Option<String>` is only
+    /// populated on that final page (per the list API's documented paging
+    /// semantics), and that `Error::TooManyObjects { limit: u32, kind: String }`
+    /// exists for the `max_objects` guard below.
+    pub async fn seed(&self) -> Result<Vec<K>> {
+        let kind = self.resource.kind.clone();
+        let span = tracing::debug_span!("informer_seed", kind = %kind);
+        async move {
+            let seed_start = Instant::now();
+            let mut params = self.params.clone();
+            params.limit = self.list_limit;
+
+            let mut objects = Vec::new();
+            let mut continue_token: Option<String> = None;
+            loop {
+                params.continue_token = continue_token.take();
+                let req = self.resource.list(&params)?;
+                let list: ObjectList<K> = self.client.request(req).await?;
+
+                match next_seed_page(objects.len(), list.items.len(), list.metadata.continue_.clone(), self.max_objects) {
+                    Err(limit) => {
+                        return Err(Error::TooManyObjects {
+                            limit,
+                            kind: self.resource.kind.clone(),
+                        })
+                    }
+                    Ok(Some(c)) => {
+                        objects.extend(list.items);
+                        continue_token = Some(c);
+                    }
+                    Ok(None) => {
+                        objects.extend(list.items);
+                        if let Some(rv) = list.metadata.resource_version {
+                            self.set_version(rv);
+                        }
+                        // We just fetched a fresh, authoritative version out-of-band -
+                        // any pending resync from a watch that preceded this seed is
+                        // now moot. Clearing it here keeps the next `poll` from calling
+                        // `reset()`, which would otherwise stomp the version we just
+                        // paged for back to "0". A pending retry is left set, so the
+                        // next `poll` after a failed one still waits out the backoff.
+                        *self.needs_resync.lock().await = false;
+                        break;
+                    }
+                }
+            }
+
+            #[cfg(feature = "metrics")]
+            metrics::histogram!(
+                "kube_informer_list_seed_duration_seconds",
+                seed_start.elapsed().as_secs_f64(),
+                "kind" => kind.clone(),
+            );
+            Ok(objects)
+        }
+        .instrument(span)
+        .await
+    }
+
     /// Initialize from a prior version
     pub fn init_from(self, v: String) -> Self {
         info!("Recreating Informer for {} at {}", self.resource.kind, v);
-
-        // We need to block on this as our mutex needs go be async compatible
-        futures::executor::block_on(async {
-            *self.version.lock().await = v;
-        });
+        self.set_version(v);
         self
     }
 
+    /// Subscribe to updates of the current resourceVersion
+    ///
+    /// Every time `poll`'s event interceptor (or `seed`) advances the
+    /// stored version, the new value is broadcast on this channel. Clone
+    /// the receiver cheaply and `changed().await` on it - e.g. to persist
+    /// the cursor to disk, or to observe liveness from another task -
+    /// instead of busy-polling `version()`.
+    pub fn version_rx(&self) -> watch::Receiver<String> {
+        self.version_rx.clone()
+    }
+
     /// Start a single watch stream
     ///
     /// Opens a long polling GET and returns the complete WatchEvents as a Stream.
@@ -66,10 +279,18 @@ where
     /// - if we go out of history (410 Gone), we reset to latest
     /// - if we failed an initial poll, we will retry
     /// All real errors are bubbled up, as are WachEvent::Error instances.
-    /// In the retry/reset cases we wait 10s between each attempt.
+    /// In the retry/reset cases we wait according to an exponential backoff
+    /// (see `Informer::with_backoff`) between each attempt.
     ///
     /// If you need to track the `resourceVersion` you can use `Informer::version()`.
     pub async fn poll(&self) -> Result<impl Stream<Item = Result<WatchEvent<K>>>> {
+        let kind = self.resource.kind.clone();
+        let resource_version = self.version_rx.borrow().clone();
+        let span = tracing::debug_span!("informer_poll", kind = %kind, version = %resource_version);
+        self.poll_inner(kind).instrument(span).await
+    }
+
+    async fn poll_inner(&self, kind: String) -> Result<impl Stream<Item = Result<WatchEvent<K>>>> {
         trace!("Watching {}", self.resource.kind);
 
         // First check if we need to backoff or reset our resourceVersion from last time
@@ -77,8 +298,28 @@ where
             let mut needs_retry = self.needs_retry.lock().await;
             let mut needs_resync = self.needs_resync.lock().await;
             if *needs_resync || *needs_retry {
-                // Try again in a bit
-                let dur = Duration::from_secs(10);
+                // Connection-duration is recorded where the watch stream actually
+                // ends (see the `stream::unfold` wrapper below), not here - this
+                // branch only fires on the *next* poll, and by then a long-lived,
+                // healthy connection has already had `connected_since` cleared by
+                // the backoff-reset logic, which would make this metric only ever
+                // see short, unhealthy connections.
+                #[cfg(feature = "metrics")]
+                metrics::counter!(
+                    "kube_informer_watch_restarts_total", 1,
+                    "kind" => kind.clone(),
+                    "cause" => if *needs_resync { "resync" } else { "retry" },
+                );
+
+                // We didn't stay connected long enough to consider ourselves healthy
+                *self.connected_since.lock().await = None;
+                // Try again after an exponentially increasing delay
+                let dur = self
+                    .backoff
+                    .lock()
+                    .unwrap()
+                    .next_backoff()
+                    .unwrap_or_else(|| Duration::from_secs(60));
                 delay_for(dur).await;
                 // If we are outside history, start over from latest
                 if *needs_resync {
@@ -90,34 +331,58 @@ where
         }
 
         // Create our watch request
-        let resource_version = self.version.lock().await.clone();
-        let req = self.resource.watch(&self.params, &resource_version)?;
+        let resource_version = self.version_rx.borrow().clone();
+        let mut params = self.params.clone();
+        params.bookmarks = self.bookmarks;
+        let req = self.resource.watch(&params, &resource_version)?;
 
         // Clone Arcs for stream handling
-        let version = self.version.clone();
+        let version_tx = self.version_tx.clone();
         let needs_resync = self.needs_resync.clone();
+        let backoff = self.backoff.clone();
+        let connected_since = self.connected_since.clone();
+        let store = self.store.clone();
+        let last_saved = self.last_saved.clone();
 
         // Attempt to fetch our stream
         let stream = self.client.request_events::<WatchEvent<K>>(req).await;
 
         match stream {
             Ok(events) => {
+                // We have a live connection - start the health clock for backoff resets
+                let watch_started = Instant::now();
+                *connected_since.lock().await = Some(watch_started);
+
+                // Cloned for the teardown health check below - the per-event
+                // closure below takes ownership of its own clones of these.
+                let backoff_for_teardown = backoff.clone();
+                let connected_since_for_teardown = connected_since.clone();
+
                 // Intercept stream elements to update internal resourceVersion
-                Ok(events.then(move |event| {
+                let intercepted = events.then(move |event| {
                     // Need to clone our Arcs as they are consumed each loop
                     let needs_resync = needs_resync.clone();
-                    let version = version.clone();
+                    let version_tx = version_tx.clone();
+                    let backoff = backoff.clone();
+                    let connected_since = connected_since.clone();
+                    let store = store.clone();
+                    let last_saved = last_saved.clone();
+                    let kind = kind.clone();
+                    let event_span = tracing::trace_span!("informer_event", kind = %kind);
                     async move {
                         // Check if we need to update our version based on the incoming events
-                        match &event {
+                        let new_version = match &event {
                             Ok(WatchEvent::Added(o))
                             | Ok(WatchEvent::Modified(o))
                             | Ok(WatchEvent::Deleted(o)) => {
                                 // Follow docs conventions and store the last resourceVersion
                                 // https://kubernetes.io/docs/reference/using-api/api-concepts/#efficient-detection-of-changes
-                                if let Some(nv) = Meta::resource_ver(o) {
-                                    *version.lock().await = nv.clone();
-                                }
+                                Meta::resource_ver(o).cloned()
+                            }
+                            Ok(WatchEvent::Bookmark(b)) => {
+                                // No object payload - just an updated cursor we should
+                                // track without surfacing this as a meaningful change
+                                Some(b.resource_version.clone())
                             }
                             Ok(WatchEvent::Error(e)) => {
                                 // 410 Gone => we need to restart from latest next call
@@ -125,19 +390,90 @@ where
                                     warn!("Stream desynced: {:?}", e);
                                     *needs_resync.lock().await = true;
                                 }
+                                None
                             }
                             Err(e) => {
                                 warn!("Unexpected watch error: {:?}", e);
+                                None
                             }
                         };
+                        if let Some(nv) = new_version {
+                            if let Some(store) = &store {
+                                // Debounced: we only need the persisted cursor to be
+                                // roughly current, not exact on every single event
+                                let mut last_saved = last_saved.lock().await;
+                                let due = last_saved.map_or(true, |t| t.elapsed() >= VERSION_SAVE_DEBOUNCE);
+                                if due {
+                                    store.save(&nv).await;
+                                    *last_saved = Some(Instant::now());
+                                }
+                            }
+                            #[cfg(feature = "metrics")]
+                            if let Ok(v) = nv.parse::<f64>() {
+                                metrics::gauge!("kube_informer_resource_version", v, "kind" => kind.clone());
+                            }
+                            let _ = version_tx.broadcast(nv);
+                            // Only reset the backoff once this connection has proven
+                            // itself healthy for a sustained period of time
+                            let mut connected_since = connected_since.lock().await;
+                            if let Some(since) = *connected_since {
+                                if since.elapsed() >= MIN_HEALTHY_DURATION {
+                                    backoff.lock().unwrap().reset();
+                                    *connected_since = None;
+                                }
+                            }
+                        }
                         event
                     }
+                    .instrument(event_span)
+                });
+
+                // Record how long this watch connection stayed alive once the
+                // stream actually ends (apiserver timeout, dropped connection,
+                // etc), rather than only on the next poll's teardown branch -
+                // that would miss clean ends entirely and undercount healthy,
+                // long-lived connections whose `connected_since` has already
+                // been cleared by the backoff-reset logic above.
+                let kind = kind.clone();
+                Ok(stream::unfold(Box::pin(intercepted), move |mut inner| {
+                    let kind = kind.clone();
+                    let backoff = backoff_for_teardown.clone();
+                    let connected_since = connected_since_for_teardown.clone();
+                    async move {
+                        match inner.next().await {
+                            Some(item) => Some((item, inner)),
+                            None => {
+                                #[cfg(feature = "metrics")]
+                                metrics::histogram!(
+                                    "kube_informer_watch_connection_duration_seconds",
+                                    watch_started.elapsed().as_secs_f64(),
+                                    "kind" => kind,
+                                );
+                                #[cfg(not(feature = "metrics"))]
+                                let _ = (kind, watch_started);
+                                // A quiet connection (no bookmarks, no data events) never
+                                // hits the per-event health check above, so it would
+                                // otherwise never get to prove itself healthy and reset
+                                // the backoff - do the same check here at stream end,
+                                // which this connection reaches regardless of whether it
+                                // ever produced an event.
+                                let mut connected_since = connected_since.lock().await;
+                                if let Some(since) = *connected_since {
+                                    if since.elapsed() >= MIN_HEALTHY_DURATION {
+                                        backoff.lock().unwrap().reset();
+                                        *connected_since = None;
+                                    }
+                                }
+                                None
+                            }
+                        }
+                    }
                 }))
             }
             Err(e) => {
                 warn!("Poll error: {:?}", e);
                 // If we failed to do the main watch - try again later with same version
-                *self.needs_retry.lock().await = false;
+                *self.needs_retry.lock().await = true;
                 Err(e)
             }
         }
@@ -147,13 +483,61 @@ where
     ///
     /// Note: This will cause duplicate Added events for all existing resources
     pub async fn reset(&self) {
-        *self.version.lock().await = 0.to_string();
+        self.set_version(0.to_string());
     }
 
     /// Return the current version
     pub fn version(&self) -> String {
-        // We need to block on a future here quickly
-        // to get a lock on our version
-        futures::executor::block_on(async { self.version.lock().await.clone() })
+        self.version_rx.borrow().clone()
+    }
+}
+
+/// Decide what `seed`'s pagination loop should do with one listed page
+///
+/// Returns `Ok(Some(token))` to fetch the next page with `token` as the
+/// `continue` param, `Ok(None)` once `continue_` comes back empty (the last
+/// page), or `Err(limit)` if accepting this page would put the running
+/// total over `max_objects`.
+fn next_seed_page(
+    accumulated: usize,
+    page_len: usize,
+    continue_: Option<String>,
+    max_objects: Option<u32>,
+) -> std::result::Result<Option<String>, u32> {
+    if let Some(max) = max_objects {
+        if accumulated + page_len > max as usize {
+            return Err(max);
+        }
+    }
+    Ok(continue_.filter(|c| !c.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::next_seed_page;
+
+    #[test]
+    fn follows_a_non_empty_continue_token() {
+        assert_eq!(next_seed_page(0, 10, Some("abc".to_owned()), None), Ok(Some("abc".to_owned())));
+    }
+
+    #[test]
+    fn stops_once_the_continue_token_is_empty() {
+        assert_eq!(next_seed_page(10, 5, Some(String::new()), None), Ok(None));
+    }
+
+    #[test]
+    fn stops_when_there_is_no_continue_token() {
+        assert_eq!(next_seed_page(10, 5, None, None), Ok(None));
+    }
+
+    #[test]
+    fn allows_a_page_landing_exactly_on_max_objects() {
+        assert_eq!(next_seed_page(8, 2, Some("next".to_owned()), Some(10)), Ok(Some("next".to_owned())));
+    }
+
+    #[test]
+    fn rejects_a_page_that_would_exceed_max_objects() {
+        assert_eq!(next_seed_page(8, 3, Some("next".to_owned()), Some(10)), Err(10));
     }
 }
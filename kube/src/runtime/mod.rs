@@ -0,0 +1,7 @@
+mod informer;
+mod version_store;
+mod watcher;
+
+pub use informer::Informer;
+pub use version_store::{FileVersionStore, VersionStore};
+pub use watcher::{watcher, Event};
@@ -0,0 +1,59 @@
+use async_trait::async_trait;
+use std::path::PathBuf;
+use tokio::fs;
+
+/// A pluggable store for the watch cursor (`resourceVersion`) an `Informer` uses
+///
+/// Implement this to give an `Informer` durability across restarts: on
+/// construction it consults `load` to resume where it left off instead of
+/// doing a full relist, and the poll interceptor calls `save` as the
+/// resourceVersion advances, at most once every couple of seconds: a version
+/// that arrives within that window after the last save is not written.
+#[async_trait]
+pub trait VersionStore {
+    /// Load the last persisted resourceVersion, if any
+    async fn load(&self) -> Option<String>;
+
+    /// Persist the current resourceVersion
+    async fn save(&self, v: &str);
+}
+
+/// A `VersionStore` that atomically writes the resourceVersion to a file
+///
+/// Mirrors how a durable resync queue keeps its position across restarts:
+/// `save` writes to a sibling temp file and renames it into place so a
+/// crash mid-write never leaves a truncated cursor behind.
+pub struct FileVersionStore {
+    path: PathBuf,
+}
+
+impl FileVersionStore {
+    /// Persist the version to (and restore it from) the given path
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileVersionStore { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl VersionStore for FileVersionStore {
+    async fn load(&self) -> Option<String> {
+        match fs::read_to_string(&self.path).await {
+            Ok(v) => Some(v.trim().to_owned()),
+            Err(e) => {
+                trace!("No persisted resourceVersion at {:?}: {}", self.path, e);
+                None
+            }
+        }
+    }
+
+    async fn save(&self, v: &str) {
+        let tmp_path = self.path.with_extension("tmp");
+        if let Err(e) = fs::write(&tmp_path, v).await {
+            warn!("Failed to write resourceVersion to {:?}: {}", tmp_path, e);
+            return;
+        }
+        if let Err(e) = fs::rename(&tmp_path, &self.path).await {
+            warn!("Failed to persist resourceVersion to {:?}: {}", self.path, e);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{
+    api::{Meta, WatchEvent},
+    runtime::Informer,
+    Result,
+};
+
+use futures::{stream, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::pin::Pin;
+
+/// A semantic event emitted by `watcher`
+///
+/// Unlike a raw `WatchEvent`, this flattens `Added`/`Modified` into a single
+/// `Applied`, and surfaces a full relist as one `Restarted` carrying every
+/// object that currently exists - so a consumer never needs to special-case
+/// a resync to avoid missing deletions.
+#[derive(Clone, Debug)]
+pub enum Event<K> {
+    /// An object was added or modified
+    Applied(K),
+    /// An object was deleted
+    Deleted(K),
+    /// A relist happened; this is the complete, current set of objects
+    Restarted(Vec<K>),
+}
+
+/// Internal state machine driving `watcher`
+enum State<K>
+where
+    K: Clone + DeserializeOwned + Meta,
+{
+    /// Needs an initial list to seed the resourceVersion
+    Empty,
+    /// Listed, now needs to start a watch from `resource_version`
+    InitListed { resource_version: String },
+    /// Watching from `resource_version`, forwarding events as they arrive
+    Watching {
+        resource_version: String,
+        stream: Pin<Box<dyn Stream<Item = Result<WatchEvent<K>>> + Send>>,
+    },
+}
+
+/// Whether a watch error with the given HTTP-style code means our cursor
+/// has fallen out of the apiserver's history and needs a fresh relist
+/// (`State::Empty`), or is transient noise that can be ridden out on the
+/// same watch.
+///
+/// `None` stands for the watch stream ending outright (connection dropped,
+/// apiserver timeout) rather than an explicit error - that always needs a
+/// relist too, since there's no live connection left to retry on.
+fn should_relist(watch_error_code: Option<u16>) -> bool {
+    match watch_error_code {
+        None => true,
+        Some(code) => code == 410,
+    }
+}
+
+/// Watch a `Resource<K>` as an infinite stream of semantic `Event<K>`s
+///
+/// This is built on top of `Informer` to remove the resourceVersion
+/// bookkeeping, 410 handling, and "poll again when the stream ends" loop
+/// that every `Informer::poll` consumer would otherwise have to reimplement.
+/// The returned stream never ends on its own: after any desync (a 410 Gone,
+/// or the underlying watch simply dropping) it transparently relists and
+/// emits a fresh `Event::Restarted` with the complete current state, so a
+/// `for_each` over this stream is always driving a self-healing cache.
+///
+/// Takes an already-configured `Informer` rather than bare `(Client,
+/// ListParams, Resource)` so that `Informer::with_backoff`,
+/// `with_bookmarks`, `with_list_limit`/`with_max_objects`, and
+/// `with_version_store` all compose with `watcher` - build the `Informer`
+/// with whichever of those you need, then hand it to `watcher`.
+///
+/// If `informer` already holds a resourceVersion (e.g. `with_version_store`
+/// resumed one, or it was built via `init_from`), the stream starts straight
+/// from a watch at that version instead of relisting - a `with_version_store`
+/// resume only pays for a full `seed()` the first time it has no persisted
+/// cursor to resume from.
+pub fn watcher<K>(informer: Informer<K>) -> impl Stream<Item = Result<Event<K>>>
+where
+    K: Clone + DeserializeOwned + Meta + Send + 'static,
+{
+    let initial_state = match informer.version() {
+        v if v == "0" => State::Empty,
+        resource_version => State::InitListed { resource_version },
+    };
+    stream::unfold((initial_state, informer), move |(mut state, mut informer)| {
+        async move {
+            loop {
+                match state {
+                    State::Empty => match informer.seed().await {
+                        Ok(objects) => {
+                            let resource_version = informer.version();
+                            return Some((
+                                Ok(Event::Restarted(objects)),
+                                (State::InitListed { resource_version }, informer),
+                            ));
+                        }
+                        Err(e) => return Some((Err(e), (State::Empty, informer))),
+                    },
+                    State::InitListed { resource_version } => match informer.poll().await {
+                        Ok(raw) => {
+                            state = State::Watching {
+                                resource_version,
+                                stream: Box::pin(raw),
+                            };
+                        }
+                        Err(e) => return Some((Err(e), (State::Empty, informer))),
+                    },
+                    State::Watching {
+                        resource_version,
+                        mut stream,
+                    } => match stream.next().await {
+                        Some(Ok(WatchEvent::Added(o))) | Some(Ok(WatchEvent::Modified(o))) => {
+                            let resource_version =
+                                Meta::resource_ver(&o).cloned().unwrap_or(resource_version);
+                            return Some((
+                                Ok(Event::Applied(o)),
+                                (State::Watching { resource_version, stream }, informer),
+                            ));
+                        }
+                        Some(Ok(WatchEvent::Deleted(o))) => {
+                            let resource_version =
+                                Meta::resource_ver(&o).cloned().unwrap_or(resource_version);
+                            return Some((
+                                Ok(Event::Deleted(o)),
+                                (State::Watching { resource_version, stream }, informer),
+                            ));
+                        }
+                        Some(Ok(WatchEvent::Bookmark(_))) => {
+                            // Just a cursor advance, already applied by the informer's
+                            // poll interceptor - nothing to surface to the consumer
+                            state = State::Watching { resource_version, stream };
+                        }
+                        Some(Ok(WatchEvent::Error(e))) => {
+                            if should_relist(Some(e.code)) {
+                                warn!("Watch desynced: {:?}, relisting", e);
+                                state = State::Empty;
+                            } else {
+                                warn!("Unexpected watch error: {:?}", e);
+                                state = State::Watching { resource_version, stream };
+                            }
+                        }
+                        Some(Err(e)) => {
+                            return Some((Err(e), (State::Watching { resource_version, stream }, informer)));
+                        }
+                        None => {
+                            // The underlying watch stream ended; relist to get a fresh cursor
+                            state = State::Empty;
+                        }
+                    },
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::should_relist;
+
+    #[test]
+    fn relists_on_410_gone() {
+        assert!(should_relist(Some(410)));
+    }
+
+    #[test]
+    fn stays_on_watch_for_other_error_codes() {
+        assert!(!should_relist(Some(500)));
+        assert!(!should_relist(Some(0)));
+    }
+
+    #[test]
+    fn relists_when_the_watch_stream_ends() {
+        assert!(should_relist(None));
+    }
+}